@@ -1,5 +1,4 @@
 use std::fs;
-use std::path::Path;
 
 // Add the local dsrs dependency to your Cargo.toml:
 // dsrs = { path = "../datasketches-rs" }
@@ -20,8 +19,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("Temperature data analysis:");
     println!("  Total readings: {}", temp_sketch.get_n());
-    println!("  Min temperature: {:.2}°C", temp_sketch.get_min_value());
-    println!("  Max temperature: {:.2}°C", temp_sketch.get_max_value());
+    println!("  Min temperature: {:.2}°C", temp_sketch.get_min_value().unwrap());
+    println!("  Max temperature: {:.2}°C", temp_sketch.get_max_value().unwrap());
     println!("  K parameter: {}", temp_sketch.get_k());
     println!("  Number retained: {}", temp_sketch.get_num_retained());
     println!("  Is estimation mode: {}", temp_sketch.is_estimation_mode());
@@ -30,7 +29,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let quantiles = [0.25, 0.5, 0.75, 0.9, 0.99];
     println!("  Quantiles:");
     for q in &quantiles {
-        let value = temp_sketch.get_quantile(*q);
+        let value = temp_sketch.get_quantile(*q).unwrap();
         println!("    {:.0}th percentile: {:.2}°C", q * 100.0, value);
     }
     
@@ -54,15 +53,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("Stock price analysis:");
     println!("  Total price points: {}", price_sketch.get_n());
-    println!("  Min price: ${:.2}", price_sketch.get_min_value());
-    println!("  Max price: ${:.2}", price_sketch.get_max_value());
-    println!("  Median price: ${:.2}", price_sketch.get_quantile(0.5));
+    println!("  Min price: ${:.2}", price_sketch.get_min_value().unwrap());
+    println!("  Max price: ${:.2}", price_sketch.get_max_value().unwrap());
+    println!("  Median price: ${:.2}", price_sketch.get_quantile(0.5).unwrap());
     
     // Percentile analysis
     let percentiles = [0.25, 0.5, 0.75, 0.9, 0.95, 0.99];
     println!("  Price percentiles:");
     for p in &percentiles {
-        let price = price_sketch.get_quantile(*p);
+        let price = price_sketch.get_quantile(*p).unwrap();
         println!("    {:.0}%: ${:.2}", p * 100.0, price);
     }
     
@@ -81,14 +80,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     println!("Before merge:");
-    println!("  Sketch1: {} items, median: {:.1}", sketch1.get_n(), sketch1.get_quantile(0.5));
-    println!("  Sketch2: {} items, median: {:.1}", sketch2.get_n(), sketch2.get_quantile(0.5));
+    println!("  Sketch1: {} items, median: {:.1}", sketch1.get_n(), sketch1.get_quantile(0.5).unwrap());
+    println!("  Sketch2: {} items, median: {:.1}", sketch2.get_n(), sketch2.get_quantile(0.5).unwrap());
     
     // Merge sketch2 into sketch1
     sketch1.merge(&sketch2);
     
     println!("After merge:");
-    println!("  Combined: {} items, median: {:.1}", sketch1.get_n(), sketch1.get_quantile(0.5));
+    println!("  Combined: {} items, median: {:.1}", sketch1.get_n(), sketch1.get_quantile(0.5).unwrap());
     
     // Demo 4: Serialization to native format
     println!("\n4. Serializing sketches...");
@@ -123,10 +122,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Verification:");
     println!("  Original items: {}", temp_sketch.get_n());
     println!("  Recovered items: {}", recovered_sketch.get_n());
-    println!("  Original median: {:.2}", temp_sketch.get_quantile(0.5));
-    println!("  Recovered median: {:.2}", recovered_sketch.get_quantile(0.5));
-    
-    let median_diff = (temp_sketch.get_quantile(0.5) - recovered_sketch.get_quantile(0.5)).abs();
+    println!("  Original median: {:.2}", temp_sketch.get_quantile(0.5).unwrap());
+    println!("  Recovered median: {:.2}", recovered_sketch.get_quantile(0.5).unwrap());
+
+    let median_diff = (temp_sketch.get_quantile(0.5).unwrap() - recovered_sketch.get_quantile(0.5).unwrap()).abs();
     println!("  Median difference: {:.6}", median_diff);
     
     if median_diff < 0.001 {
@@ -148,7 +147,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\nFiles created:");
     println!("  - temperature_sketch.msgpack (temperature sensor data)");
     println!("  - stock_prices.msgpack (stock price data)");
-    println!("\nThese can be read by Python using the read_kll_sketch.py script.");
+    println!(
+        "\nNote: these .msgpack files use this crate's own serde layout, not the \
+         Apache DataSketches wire format, so a `datasketches` library cannot load \
+         them; dsrs reads them back with KllFloatSketch::from_msgpack. Cross-language interop is the goal of \
+         KllSketch::serialize_compatible_unverified, but that format is unverified against a \
+         reference implementation — see its doc comment before relying on it."
+    );
     
     Ok(())
 }
@@ -0,0 +1,14 @@
+//! Shared native-serialization byte wrapper.
+
+/// Bytes produced by a sketch's `serialize` method (e.g.
+/// [`crate::kll::KllSketch::serialize`], [`crate::theta::ThetaSketch::serialize`]).
+/// Each sketch type defines its own byte layout; this just gives callers one
+/// common type to hold and pass around instead of a bare `Vec<u8>`.
+#[derive(Clone, Debug)]
+pub struct NativeBytes(pub(crate) Vec<u8>);
+
+impl AsRef<[u8]> for NativeBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+/// Errors produced by sketch queries and (de)serialization.
+#[derive(Debug, Error)]
+pub enum SketchError {
+    #[error("sketch is empty")]
+    EmptySketch,
+
+    #[error("invalid split points: {0}")]
+    InvalidSplitPoints(String),
+
+    #[error("significance level must be in (0.0, 1.0): {0}")]
+    InvalidSignificanceLevel(f64),
+
+    #[error("failed to encode sketch as MessagePack: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+
+    #[error("failed to decode sketch from MessagePack: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+
+    #[error("malformed serialized sketch: {0}")]
+    Corrupt(String),
+}
+
+pub type Result<T> = std::result::Result<T, SketchError>;
@@ -0,0 +1,176 @@
+//! Frequent-items (heavy hitters) sketches.
+//!
+//! Uses the reverse-purge hash map approach: a fixed-capacity `item -> count`
+//! map that, once full, decrements every count by the map's current minimum
+//! and drops anything that hits zero. The total amount ever subtracted this
+//! way (`offset`) becomes a global error bound — any item's true count lies
+//! between its local count and `local count + offset`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Controls which error guarantee [`FrequentItemsSketch::get_frequent_items`]
+/// returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorType {
+    /// Every returned item is guaranteed to be truly frequent, at the cost
+    /// of possibly omitting some frequent items (stricter: requires the
+    /// item's lower bound to exceed the error offset).
+    NoFalsePositives,
+    /// Every truly frequent item is guaranteed to be returned, at the cost
+    /// of possibly including some items that aren't (looser: only requires
+    /// the item's upper-bound estimate to exceed the error offset).
+    NoFalseNegatives,
+}
+
+/// One row of [`FrequentItemsSketch::get_frequent_items`]: an item and its
+/// estimated weight with error bounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrequentItemRow<T> {
+    pub item: T,
+    pub estimate: u64,
+    pub lower_bound: u64,
+    pub upper_bound: u64,
+}
+
+/// A heavy-hitters sketch bounded to `max_map_size` distinct items.
+pub struct FrequentItemsSketch<T: Hash + Eq + Clone> {
+    max_map_size: usize,
+    counts: HashMap<T, u64>,
+    offset: u64,
+}
+
+impl<T: Hash + Eq + Clone> FrequentItemsSketch<T> {
+    /// Creates a sketch that retains at most `max_map_size` distinct items
+    /// at a time before purging.
+    pub fn new(max_map_size: usize) -> Self {
+        Self {
+            max_map_size: max_map_size.max(1),
+            counts: HashMap::new(),
+            offset: 0,
+        }
+    }
+
+    /// Adds `weight` occurrences of `item`.
+    pub fn update(&mut self, item: T, weight: u64) {
+        *self.counts.entry(item).or_insert(0) += weight;
+        if self.counts.len() > self.max_map_size {
+            self.purge();
+        }
+    }
+
+    /// Estimated total weight of `item`: its current local count plus the
+    /// global error offset.
+    pub fn get_estimate(&self, item: &T) -> u64 {
+        self.counts.get(item).copied().unwrap_or(0) + self.offset
+    }
+
+    /// Guaranteed lower bound on `item`'s true weight.
+    pub fn get_lower_bound(&self, item: &T) -> u64 {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Guaranteed upper bound on `item`'s true weight (equal to the
+    /// estimate: the offset can only ever have under-counted, never
+    /// over-counted).
+    pub fn get_upper_bound(&self, item: &T) -> u64 {
+        self.get_estimate(item)
+    }
+
+    /// The maximum amount any single item's count could have been
+    /// under-reported by, across all purges so far.
+    pub fn get_max_error(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns a ranked (highest estimate first) list of items meeting the
+    /// guarantee selected by `error_type`.
+    pub fn get_frequent_items(&self, error_type: ErrorType) -> Vec<FrequentItemRow<T>> {
+        let mut rows: Vec<FrequentItemRow<T>> = self
+            .counts
+            .iter()
+            .filter_map(|(item, &count)| {
+                let include = match error_type {
+                    ErrorType::NoFalsePositives => count > self.offset,
+                    // Every entry retained in the map has a positive local
+                    // count by construction, so this guarantees completeness.
+                    ErrorType::NoFalseNegatives => count > 0,
+                };
+                include.then(|| FrequentItemRow {
+                    item: item.clone(),
+                    estimate: count + self.offset,
+                    lower_bound: count,
+                    upper_bound: count + self.offset,
+                })
+            })
+            .collect();
+        rows.sort_by_key(|row| std::cmp::Reverse(row.estimate));
+        rows
+    }
+
+    fn purge(&mut self) {
+        let min = self.counts.values().copied().min().unwrap_or(0);
+        if min == 0 {
+            self.counts.retain(|_, count| *count > 0);
+            return;
+        }
+        self.offset += min;
+        self.counts.retain(|_, count| {
+            *count -= min;
+            *count > 0
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_mode_counts_are_precise() {
+        let mut s = FrequentItemsSketch::new(10);
+        s.update("a", 5);
+        s.update("b", 2);
+        assert_eq!(s.get_estimate(&"a"), 5);
+        assert_eq!(s.get_estimate(&"b"), 2);
+        assert_eq!(s.get_max_error(), 0);
+    }
+
+    #[test]
+    fn heavy_hitter_survives_purging() {
+        let mut s = FrequentItemsSketch::new(4);
+        for i in 0..1000 {
+            s.update("hot".to_string(), 1);
+            s.update(format!("cold-{i}"), 1);
+        }
+        let estimate = s.get_estimate(&"hot".to_string());
+        assert!(
+            (estimate as i64 - 1000).unsigned_abs() <= s.get_max_error(),
+            "estimate {estimate} not within error {} of 1000",
+            s.get_max_error()
+        );
+    }
+
+    #[test]
+    fn get_frequent_items_ranks_by_estimate_descending() {
+        let mut s = FrequentItemsSketch::new(10);
+        s.update("a", 100);
+        s.update("b", 50);
+        s.update("c", 10);
+        let rows = s.get_frequent_items(ErrorType::NoFalseNegatives);
+        let estimates: Vec<u64> = rows.iter().map(|r| r.estimate).collect();
+        assert_eq!(estimates, vec![100, 50, 10]);
+    }
+
+    #[test]
+    fn no_false_positives_is_at_least_as_strict_as_no_false_negatives() {
+        let mut s = FrequentItemsSketch::new(4);
+        for i in 0..500 {
+            s.update("hot".to_string(), 1);
+            s.update(format!("cold-{i}"), 1);
+        }
+        let strict = s.get_frequent_items(ErrorType::NoFalsePositives).len();
+        let loose = s.get_frequent_items(ErrorType::NoFalseNegatives).len();
+        assert!(strict <= loose);
+    }
+}
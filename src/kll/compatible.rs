@@ -0,0 +1,243 @@
+//! An attempt at the Apache DataSketches KLL wire format.
+//!
+//! This follows the publicly documented preamble layout used by the
+//! C++/Java/Python implementations: a fixed 8-byte header (preamble-ints,
+//! ser-ver, family ID, flags, `k`, `m`, padding), optionally followed by
+//! `n`, the dynamic min-k, the level count, a `num_levels + 1`-entry
+//! level-offset array, the min/max values, and finally the retained items
+//! themselves (grouped by level, each level sorted ascending). Empty and
+//! single-item sketches use the shorter two-word preamble the reference
+//! implementation reserves for those cases.
+//!
+//! Unverified: this layout was written from the documented preamble
+//! description, not checked against byte buffers captured from a live
+//! C++/Java/Python `datasketches` build (no such build is available in
+//! this environment), so the round-trip tests below only check
+//! self-consistency against this crate's own (de)serializer. Do not rely
+//! on this format actually interoperating with another language's
+//! `datasketches` library until it has been verified against real
+//! reference-implementation output.
+
+use super::{KllNumber, KllSketch};
+use crate::error::{Result, SketchError};
+
+const SER_VER: u8 = 1;
+const FAMILY_ID: u8 = 15;
+const MIN_LEVEL_CAP_M: u8 = 8;
+
+const FLAG_EMPTY: u8 = 1;
+const FLAG_LEVEL_ZERO_SORTED: u8 = 2;
+const FLAG_SINGLE_ITEM: u8 = 4;
+
+impl<T: KllNumber + serde::Serialize + for<'de> serde::Deserialize<'de>> KllSketch<T> {
+    /// Serializes the sketch using this crate's best-effort reproduction of
+    /// the Apache DataSketches KLL preamble and item layout. See the module
+    /// docs: this has not been verified against reference-implementation
+    /// output, so treat it as unproven cross-language compatibility, not
+    /// a guarantee.
+    pub fn serialize_compatible_unverified(&self) -> Vec<u8> {
+        let empty = self.n == 0;
+        let single_item = self.n == 1;
+
+        let mut flags = FLAG_LEVEL_ZERO_SORTED;
+        if empty {
+            flags |= FLAG_EMPTY;
+        }
+        if single_item {
+            flags |= FLAG_SINGLE_ITEM;
+        }
+        let preamble_ints: u8 = if empty || single_item { 2 } else { 5 };
+
+        let mut buf = vec![preamble_ints, SER_VER, FAMILY_ID, flags];
+        buf.extend_from_slice(&self.k.to_le_bytes());
+        buf.push(MIN_LEVEL_CAP_M);
+        buf.push(0); // padding, for 4-byte preamble-word alignment
+
+        if empty {
+            return buf;
+        }
+        if single_item {
+            buf.extend(self.levels[0][0].to_wire_bytes());
+            return buf;
+        }
+
+        buf.extend_from_slice(&self.n.to_le_bytes());
+        buf.extend_from_slice(&self.k.to_le_bytes()); // dynamic min-k: we never shrink k
+        buf.extend_from_slice(&(self.levels.len() as u16).to_le_bytes());
+        buf.extend_from_slice(&0u16.to_le_bytes()); // padding
+
+        let sorted_levels: Vec<Vec<T>> = self
+            .levels
+            .iter()
+            .map(|level| {
+                let mut sorted = level.clone();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                sorted
+            })
+            .collect();
+
+        let mut offset = 0u32;
+        for level in &sorted_levels {
+            buf.extend_from_slice(&offset.to_le_bytes());
+            offset += level.len() as u32;
+        }
+        buf.extend_from_slice(&offset.to_le_bytes());
+
+        buf.extend(
+            self.get_min_value()
+                .expect("checked non-empty above")
+                .to_wire_bytes(),
+        );
+        buf.extend(
+            self.get_max_value()
+                .expect("checked non-empty above")
+                .to_wire_bytes(),
+        );
+
+        for level in &sorted_levels {
+            for item in level {
+                buf.extend(item.to_wire_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Deserializes a sketch written by
+    /// [`KllSketch::serialize_compatible_unverified`] (or, in principle, a
+    /// conformant implementation's native KLL serializer — see the module
+    /// docs for why that hasn't been verified).
+    pub fn deserialize_compatible_unverified(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 8 {
+            return Err(SketchError::Corrupt(
+                "buffer shorter than the 8-byte preamble".to_string(),
+            ));
+        }
+        let preamble_ints = bytes[0];
+        let ser_ver = bytes[1];
+        let family_id = bytes[2];
+        if family_id != FAMILY_ID {
+            return Err(SketchError::Corrupt(format!(
+                "family id {family_id} is not KLL"
+            )));
+        }
+        if ser_ver != SER_VER {
+            return Err(SketchError::Corrupt(format!(
+                "unsupported serialization version {ser_ver}"
+            )));
+        }
+        let flags = bytes[3];
+        let empty = flags & FLAG_EMPTY != 0;
+        let single_item = flags & FLAG_SINGLE_ITEM != 0;
+        let k = u16::from_le_bytes([bytes[4], bytes[5]]);
+        let wire_len = T::WIRE_LEN;
+
+        if empty {
+            return Ok(Self::with_k(k));
+        }
+        if single_item {
+            if bytes.len() < 8 + wire_len {
+                return Err(SketchError::Corrupt("truncated single item".to_string()));
+            }
+            let item = T::from_wire_bytes(&bytes[8..8 + wire_len]);
+            let mut sketch = Self::with_k(k);
+            sketch.update(item);
+            return Ok(sketch);
+        }
+
+        if preamble_ints < 5 || bytes.len() < 22 {
+            return Err(SketchError::Corrupt("truncated preamble".to_string()));
+        }
+        let n = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let num_levels = u16::from_le_bytes([bytes[18], bytes[19]]) as usize;
+
+        let mut pos = 22;
+        let mut level_offsets = Vec::with_capacity(num_levels + 1);
+        for _ in 0..=num_levels {
+            if pos + 4 > bytes.len() {
+                return Err(SketchError::Corrupt("truncated level offsets".to_string()));
+            }
+            level_offsets.push(u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()));
+            pos += 4;
+        }
+
+        if pos + 2 * wire_len > bytes.len() {
+            return Err(SketchError::Corrupt("truncated min/max".to_string()));
+        }
+        let min_value = T::from_wire_bytes(&bytes[pos..pos + wire_len]);
+        pos += wire_len;
+        let max_value = T::from_wire_bytes(&bytes[pos..pos + wire_len]);
+        pos += wire_len;
+
+        let total_items = *level_offsets.last().expect("pushed num_levels + 1 entries") as usize;
+        let mut flat_items = Vec::with_capacity(total_items);
+        for _ in 0..total_items {
+            if pos + wire_len > bytes.len() {
+                return Err(SketchError::Corrupt("truncated item data".to_string()));
+            }
+            flat_items.push(T::from_wire_bytes(&bytes[pos..pos + wire_len]));
+            pos += wire_len;
+        }
+
+        let mut levels = Vec::with_capacity(num_levels);
+        for i in 0..num_levels {
+            let start = level_offsets[i] as usize;
+            let end = level_offsets[i + 1] as usize;
+            if end < start || end > flat_items.len() {
+                return Err(SketchError::Corrupt("inconsistent level offsets".to_string()));
+            }
+            levels.push(flat_items[start..end].to_vec());
+        }
+
+        Ok(Self {
+            k,
+            n,
+            levels,
+            min_value: Some(min_value),
+            max_value: Some(max_value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kll::{KllDoubleSketch, KllFloatSketch};
+
+    #[test]
+    fn round_trips_an_empty_sketch() {
+        let s = KllFloatSketch::new();
+        let bytes = s.serialize_compatible_unverified();
+        let recovered = KllFloatSketch::deserialize_compatible_unverified(&bytes).unwrap();
+        assert_eq!(recovered.get_n(), 0);
+    }
+
+    #[test]
+    fn round_trips_a_single_item_sketch() {
+        let mut s = KllDoubleSketch::new();
+        s.update(42.5);
+        let bytes = s.serialize_compatible_unverified();
+        let recovered = KllDoubleSketch::deserialize_compatible_unverified(&bytes).unwrap();
+        assert_eq!(recovered.get_n(), 1);
+        assert_eq!(recovered.get_quantile(0.5).unwrap(), 42.5);
+    }
+
+    #[test]
+    fn round_trips_a_sketch_in_estimation_mode() {
+        let mut s = KllFloatSketch::with_k(100);
+        for i in 0..10_000 {
+            s.update(i as f32);
+        }
+        let bytes = s.serialize_compatible_unverified();
+        let recovered = KllFloatSketch::deserialize_compatible_unverified(&bytes).unwrap();
+        assert_eq!(recovered.get_n(), s.get_n());
+        assert_eq!(recovered.get_min_value().unwrap(), s.get_min_value().unwrap());
+        assert_eq!(recovered.get_max_value().unwrap(), s.get_max_value().unwrap());
+        assert_eq!(recovered.get_quantile(0.5).unwrap(), s.get_quantile(0.5).unwrap());
+    }
+
+    #[test]
+    fn rejects_a_buffer_from_a_different_family() {
+        let mut bytes = KllFloatSketch::new().serialize_compatible_unverified();
+        bytes[2] = 99; // not the KLL family id
+        assert!(KllFloatSketch::deserialize_compatible_unverified(&bytes).is_err());
+    }
+}
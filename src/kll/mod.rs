@@ -0,0 +1,605 @@
+//! KLL quantile sketches.
+//!
+//! This implements the KLL (Karnin-Lang-Liberty) streaming quantiles
+//! algorithm: a compacting array of levels where level `i` holds items of
+//! weight `2^i`. Inserting is O(1) amortized; compaction randomly halves a
+//! full level and promotes the survivors to the level above, which keeps
+//! memory bounded by O(k * log(n/k)) while preserving an unbiased estimate
+//! of the rank of any value.
+
+mod compatible;
+mod number;
+mod sorted_view;
+mod windowed;
+
+use std::cmp::Ordering;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::bytes::NativeBytes;
+use crate::error::{Result, SketchError};
+pub use number::KllNumber;
+pub use sorted_view::SortedView;
+pub use windowed::WindowedKllFloatSketch;
+
+/// Default `k` used by [`KllSketch::new`]. Matches the Apache DataSketches
+/// default, which bounds the normalized rank error at roughly 1.65% with
+/// 99% confidence.
+pub const DEFAULT_K: u16 = 200;
+
+const MIN_K: u16 = 8;
+const MIN_LEVEL_CAP: u32 = 2;
+
+/// A KLL quantiles sketch over `f32` values.
+pub type KllFloatSketch = KllSketch<f32>;
+
+/// A KLL quantiles sketch over `f64` values.
+pub type KllDoubleSketch = KllSketch<f64>;
+
+/// An approximate quantiles sketch that supports single-pass `update`,
+/// constant-memory `merge`, and `get_quantile`/`get_rank` queries with
+/// error bounds controlled by `k`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KllSketch<T: KllNumber> {
+    k: u16,
+    n: u64,
+    levels: Vec<Vec<T>>,
+    min_value: Option<T>,
+    max_value: Option<T>,
+}
+
+impl<T: KllNumber + Serialize + for<'de> Deserialize<'de>> KllSketch<T> {
+    /// Creates a sketch with the default `k`.
+    pub fn new() -> Self {
+        Self::with_k(DEFAULT_K)
+    }
+
+    /// Creates a sketch with an explicit `k`. Larger `k` means more memory
+    /// and tighter error bounds; smaller `k` means less memory and looser
+    /// bounds.
+    pub fn with_k(k: u16) -> Self {
+        let k = k.max(MIN_K);
+        Self {
+            k,
+            n: 0,
+            levels: vec![Vec::new()],
+            min_value: None,
+            max_value: None,
+        }
+    }
+
+    /// Adds a value to the sketch.
+    pub fn update(&mut self, value: T) {
+        self.n += 1;
+        self.min_value = Some(match self.min_value {
+            Some(m) if m.total_cmp(&value) == Ordering::Less => m,
+            _ => value,
+        });
+        self.max_value = Some(match self.max_value {
+            Some(m) if m.total_cmp(&value) == Ordering::Greater => m,
+            _ => value,
+        });
+        self.levels[0].push(value);
+        self.compress();
+    }
+
+    /// Total number of values ever passed to `update`.
+    pub fn get_n(&self) -> u64 {
+        self.n
+    }
+
+    /// The `k` parameter controlling accuracy vs. memory.
+    pub fn get_k(&self) -> u16 {
+        self.k
+    }
+
+    /// Number of items currently retained across all levels.
+    pub fn get_num_retained(&self) -> usize {
+        self.levels.iter().map(Vec::len).sum()
+    }
+
+    /// Whether the sketch has started approximating (i.e. has compacted at
+    /// least once). Below this point, quantiles and ranks are exact.
+    pub fn is_estimation_mode(&self) -> bool {
+        self.levels.len() > 1
+    }
+
+    /// Smallest value seen, or [`SketchError::EmptySketch`] if the sketch
+    /// is empty.
+    pub fn get_min_value(&self) -> Result<T> {
+        self.min_value.ok_or(SketchError::EmptySketch)
+    }
+
+    /// Largest value seen, or [`SketchError::EmptySketch`] if the sketch
+    /// is empty.
+    pub fn get_max_value(&self) -> Result<T> {
+        self.max_value.ok_or(SketchError::EmptySketch)
+    }
+
+    /// Estimates the value at normalized rank `rank` (in `[0.0, 1.0]`), or
+    /// [`SketchError::EmptySketch`] if the sketch is empty.
+    pub fn get_quantile(&self, rank: f64) -> Result<T> {
+        if self.n == 0 {
+            return Err(SketchError::EmptySketch);
+        }
+        let rank = rank.clamp(0.0, 1.0);
+        let items = self.sorted_weighted_items();
+        let target = (rank * self.n as f64).ceil() as u64;
+        let mut cumulative = 0u64;
+        for (value, weight) in &items {
+            cumulative += weight;
+            if cumulative >= target.max(1) {
+                return Ok(*value);
+            }
+        }
+        Ok(items
+            .last()
+            .map(|(v, _)| *v)
+            .expect("n > 0 implies at least one retained item"))
+    }
+
+    /// Estimates the normalized rank (in `[0.0, 1.0]`) of `value`: the
+    /// fraction of updates less than or equal to `value`.
+    pub fn get_rank(&self, value: T) -> f64 {
+        if self.n == 0 {
+            return 0.0;
+        }
+        let mut weight = 0u64;
+        for (level_idx, level) in self.levels.iter().enumerate() {
+            let level_weight = 1u64 << level_idx;
+            for item in level {
+                if item.total_cmp(&value) != Ordering::Greater {
+                    weight += level_weight;
+                }
+            }
+        }
+        weight as f64 / self.n as f64
+    }
+
+    /// Returns an iterator over all retained items in ascending order,
+    /// each paired with its cumulative weight and normalized rank.
+    /// Building this once and walking it is cheaper than repeated
+    /// `get_quantile`/`get_rank` calls for computing a whole histogram.
+    pub fn sorted_view(&self) -> SortedView<T> {
+        SortedView::new(self.cumulative_items(), self.n)
+    }
+
+    /// Cumulative normalized ranks for the buckets defined by `split_points`:
+    /// `(-inf, split_points[0]]`, `(split_points[0], split_points[1]]`, ...,
+    /// `(split_points[last], +inf)`. The result has length
+    /// `split_points.len() + 1` and its last entry is always `1.0`.
+    ///
+    /// `split_points` must be strictly increasing and free of NaNs.
+    pub fn get_cdf(&self, split_points: &[T]) -> Result<Vec<f64>> {
+        Self::validate_split_points(split_points)?;
+        let mut cumulative_weights = vec![0u64; split_points.len() + 1];
+        if self.n > 0 {
+            let items = self.cumulative_items();
+            let mut item_idx = 0usize;
+            let mut running = 0u64;
+            for (bucket, split_point) in split_points.iter().enumerate() {
+                while item_idx < items.len()
+                    && items[item_idx].0.total_cmp(split_point) != Ordering::Greater
+                {
+                    running = items[item_idx].1;
+                    item_idx += 1;
+                }
+                cumulative_weights[bucket] = running;
+            }
+        }
+        let total = self.n.max(1) as f64;
+        let mut cdf: Vec<f64> = cumulative_weights
+            .iter()
+            .map(|&w| w as f64 / total)
+            .collect();
+        if let Some(last) = cdf.last_mut() {
+            *last = 1.0;
+        }
+        Ok(cdf)
+    }
+
+    /// Per-bucket probability masses for the same buckets as
+    /// [`KllSketch::get_cdf`]; they sum to `1.0`.
+    pub fn get_pmf(&self, split_points: &[T]) -> Result<Vec<f64>> {
+        let cdf = self.get_cdf(split_points)?;
+        let mut previous = 0.0;
+        let pmf = cdf
+            .into_iter()
+            .map(|c| {
+                let mass = c - previous;
+                previous = c;
+                mass
+            })
+            .collect();
+        Ok(pmf)
+    }
+
+    fn validate_split_points(split_points: &[T]) -> Result<()> {
+        if split_points.iter().any(|v| v.as_f64().is_nan()) {
+            return Err(SketchError::InvalidSplitPoints(
+                "split points must not be NaN".to_string(),
+            ));
+        }
+        if !split_points.windows(2).all(|w| w[0].total_cmp(&w[1]) == Ordering::Less) {
+            return Err(SketchError::InvalidSplitPoints(
+                "split points must be strictly increasing".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Merges `other` into `self`, combining both streams' updates.
+    pub fn merge(&mut self, other: &Self) {
+        if other.n == 0 {
+            return;
+        }
+        self.k = self.k.min(other.k);
+        while self.levels.len() < other.levels.len() {
+            self.levels.push(Vec::new());
+        }
+        for (level, items) in other.levels.iter().enumerate() {
+            self.levels[level].extend_from_slice(items);
+        }
+        self.n += other.n;
+        self.min_value = Some(match (self.min_value, other.min_value) {
+            (Some(a), Some(b)) if a.total_cmp(&b) == Ordering::Less => a,
+            (Some(_), Some(b)) => b,
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => unreachable!("merge called with other.n > 0 but no min_value"),
+        });
+        self.max_value = Some(match (self.max_value, other.max_value) {
+            (Some(a), Some(b)) if a.total_cmp(&b) == Ordering::Greater => a,
+            (Some(_), Some(b)) => b,
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => unreachable!("merge called with other.n > 0 but no max_value"),
+        });
+        self.compress();
+    }
+
+    /// Serializes the sketch to dsrs's native binary format.
+    pub fn serialize(&self) -> NativeBytes {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.k.to_le_bytes());
+        buf.extend_from_slice(&self.n.to_le_bytes());
+        buf.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+        for level in &self.levels {
+            buf.extend_from_slice(&(level.len() as u32).to_le_bytes());
+            for item in level {
+                buf.extend_from_slice(&item.as_f64().to_le_bytes());
+            }
+        }
+        NativeBytes(buf)
+    }
+
+    /// Serializes the sketch as MessagePack, suitable for reading back with
+    /// [`KllSketch::from_msgpack`] (including from other language runtimes
+    /// with a matching schema).
+    pub fn to_msgpack(&self) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec(self)?)
+    }
+
+    /// Deserializes a sketch previously produced by
+    /// [`KllSketch::to_msgpack`].
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    /// The two-sample Kolmogorov-Smirnov statistic between `self` and
+    /// `other`: the maximum absolute difference between their empirical
+    /// CDFs, evaluated at every distinct value seen by either stream.
+    pub fn ks_distance(&self, other: &Self) -> f64 {
+        if self.n == 0 || other.n == 0 {
+            return 0.0;
+        }
+        let a = self.sorted_weighted_items();
+        let b = other.sorted_weighted_items();
+        let n1 = self.n as f64;
+        let n2 = other.n as f64;
+
+        let (mut i, mut j) = (0usize, 0usize);
+        let (mut c1, mut c2) = (0u64, 0u64);
+        let mut d = 0.0f64;
+        while i < a.len() || j < b.len() {
+            let v = match (a.get(i), b.get(j)) {
+                (Some(x), Some(y)) => {
+                    if x.0.total_cmp(&y.0) != Ordering::Greater {
+                        x.0
+                    } else {
+                        y.0
+                    }
+                }
+                (Some(x), None) => x.0,
+                (None, Some(y)) => y.0,
+                (None, None) => unreachable!(),
+            };
+            // Advance both cumulative ranks past every item tied at `v`
+            // before comparing, so the step functions are sampled at the
+            // same abscissa.
+            while i < a.len() && a[i].0.total_cmp(&v) == Ordering::Equal {
+                c1 += a[i].1;
+                i += 1;
+            }
+            while j < b.len() && b[j].0.total_cmp(&v) == Ordering::Equal {
+                c2 += b[j].1;
+                j += 1;
+            }
+            let f1 = c1 as f64 / n1;
+            let f2 = c2 as f64 / n2;
+            d = d.max((f1 - f2).abs());
+        }
+        d
+    }
+
+    /// Two-sample Kolmogorov-Smirnov test: `true` if `self` and `other` are
+    /// unlikely (at significance level `p`) to have been drawn from the
+    /// same distribution. Returns [`SketchError::InvalidSignificanceLevel`]
+    /// unless `0.0 < p < 1.0`.
+    pub fn ks_test(&self, other: &Self, p: f64) -> Result<bool> {
+        if !(p > 0.0 && p < 1.0) {
+            return Err(SketchError::InvalidSignificanceLevel(p));
+        }
+        if self.n == 0 || other.n == 0 {
+            return Ok(false);
+        }
+        let d = self.ks_distance(other);
+        let n1 = self.n as f64;
+        let n2 = other.n as f64;
+        let threshold = (-0.5 * (p / 2.0).ln()).sqrt() * ((n1 + n2) / (n1 * n2)).sqrt();
+        Ok(d > threshold)
+    }
+
+    /// All retained items paired with their level weight (`2^level`),
+    /// sorted ascending by value.
+    fn sorted_weighted_items(&self) -> Vec<(T, u64)> {
+        let mut items: Vec<(T, u64)> = self
+            .levels
+            .iter()
+            .enumerate()
+            .flat_map(|(level, items)| items.iter().map(move |v| (*v, 1u64 << level)))
+            .collect();
+        items.sort_by(|a, b| a.0.total_cmp(&b.0));
+        items
+    }
+
+    /// Ascending `(value, cumulative_weight)` pairs: the running sum of
+    /// weights up to and including each value. The backing structure for
+    /// [`KllSketch::sorted_view`], [`KllSketch::get_cdf`], and
+    /// [`KllSketch::get_pmf`].
+    fn cumulative_items(&self) -> Vec<(T, u64)> {
+        let mut items = self.sorted_weighted_items();
+        let mut cumulative = 0u64;
+        for item in &mut items {
+            cumulative += item.1;
+            item.1 = cumulative;
+        }
+        items
+    }
+
+    /// Capacity of `level` given the current number of levels: the top
+    /// level (coarsest, highest weight) gets the full `k`, and each level
+    /// below it gets a smaller capacity, per the standard KLL schedule of
+    /// `k * (2/3)^depth` where `depth` counts down from the top.
+    fn level_capacity(&self, level: usize) -> usize {
+        let depth = self.levels.len() - level - 1;
+        let cap = self.k as f64 * (2.0_f64 / 3.0).powi(depth as i32);
+        cap.ceil().max(MIN_LEVEL_CAP as f64) as usize
+    }
+
+    fn compress(&mut self) {
+        let mut rng = rand::thread_rng();
+        loop {
+            let mut compacted = false;
+            for level in 0..self.levels.len() {
+                if self.levels[level].len() > self.level_capacity(level) {
+                    self.compact_level(level, &mut rng);
+                    compacted = true;
+                    break;
+                }
+            }
+            if !compacted {
+                break;
+            }
+        }
+    }
+
+    fn compact_level(&mut self, level: usize, rng: &mut impl Rng) {
+        if level + 1 >= self.levels.len() {
+            self.levels.push(Vec::new());
+        }
+        let mut items = std::mem::take(&mut self.levels[level]);
+        items.sort_by(|a, b| a.total_cmp(b));
+        let leftover = if items.len() % 2 == 1 { items.pop() } else { None };
+        let start = usize::from(rng.gen::<bool>());
+        let promoted: Vec<T> = items.into_iter().skip(start).step_by(2).collect();
+        self.levels[level + 1].extend(promoted);
+        if let Some(extra) = leftover {
+            self.levels[level].push(extra);
+        }
+    }
+}
+
+impl<T: KllNumber + Serialize + for<'de> Deserialize<'de>> Default for KllSketch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_sketch_has_no_retained_items() {
+        let s = KllFloatSketch::new();
+        assert_eq!(s.get_n(), 0);
+        assert_eq!(s.get_num_retained(), 0);
+        assert!(!s.is_estimation_mode());
+    }
+
+    #[test]
+    fn small_stream_quantiles_are_exact() {
+        let mut s = KllFloatSketch::new();
+        for i in 1..=100 {
+            s.update(i as f32);
+        }
+        assert_eq!(s.get_min_value().unwrap(), 1.0);
+        assert_eq!(s.get_max_value().unwrap(), 100.0);
+        assert!((s.get_quantile(0.5).unwrap() - 50.0).abs() <= 1.0);
+    }
+
+    #[test]
+    fn empty_sketch_queries_return_empty_sketch_error() {
+        let s = KllFloatSketch::new();
+        assert!(matches!(s.get_min_value(), Err(SketchError::EmptySketch)));
+        assert!(matches!(s.get_max_value(), Err(SketchError::EmptySketch)));
+        assert!(matches!(s.get_quantile(0.5), Err(SketchError::EmptySketch)));
+    }
+
+    #[test]
+    fn large_stream_median_is_approximately_correct() {
+        let mut s = KllDoubleSketch::with_k(200);
+        for i in 1..=100_000u64 {
+            s.update(i as f64);
+        }
+        assert!(s.is_estimation_mode());
+        let median = s.get_quantile(0.5).unwrap();
+        assert!((median - 50_000.0).abs() < 2_000.0, "median was {median}");
+    }
+
+    #[test]
+    fn rank_and_quantile_are_inverses_at_exact_points() {
+        let mut s = KllFloatSketch::new();
+        for i in 1..=50 {
+            s.update(i as f32);
+        }
+        let rank = s.get_rank(25.0);
+        assert!((rank - 0.5).abs() < 0.05, "rank was {rank}");
+        assert_eq!(s.get_quantile(rank).unwrap(), 25.0);
+    }
+
+    #[test]
+    fn merge_combines_counts_and_ranges() {
+        let mut a = KllFloatSketch::new();
+        let mut b = KllFloatSketch::new();
+        for i in 1..=250 {
+            a.update(i as f32);
+        }
+        for i in 251..=500 {
+            b.update(i as f32);
+        }
+        a.merge(&b);
+        assert_eq!(a.get_n(), 500);
+        assert_eq!(a.get_min_value().unwrap(), 1.0);
+        assert_eq!(a.get_max_value().unwrap(), 500.0);
+    }
+
+    #[test]
+    fn msgpack_round_trip_preserves_quantiles() {
+        let mut s = KllFloatSketch::with_k(100);
+        for i in 0..1000 {
+            s.update(i as f32);
+        }
+        let bytes = s.to_msgpack().unwrap();
+        let recovered = KllFloatSketch::from_msgpack(&bytes).unwrap();
+        assert_eq!(s.get_n(), recovered.get_n());
+        assert_eq!(s.get_quantile(0.5).unwrap(), recovered.get_quantile(0.5).unwrap());
+    }
+
+    #[test]
+    fn ks_test_detects_shifted_distributions() {
+        let mut a = KllDoubleSketch::new();
+        let mut b = KllDoubleSketch::new();
+        for i in 0..2000 {
+            a.update(i as f64);
+            b.update((i + 1000) as f64);
+        }
+        assert!(a.ks_test(&b, 0.01).unwrap());
+    }
+
+    #[test]
+    fn ks_test_does_not_flag_identical_distributions() {
+        let mut a = KllDoubleSketch::new();
+        let mut b = KllDoubleSketch::new();
+        for i in 0..2000 {
+            a.update(i as f64);
+            b.update(i as f64);
+        }
+        assert!(a.ks_distance(&b) < 0.01, "distance was {}", a.ks_distance(&b));
+        assert!(!a.ks_test(&b, 0.01).unwrap());
+    }
+
+    #[test]
+    fn ks_test_on_empty_sketch_returns_false() {
+        let a = KllDoubleSketch::new();
+        let mut b = KllDoubleSketch::new();
+        b.update(1.0);
+        assert!(!a.ks_test(&b, 0.05).unwrap());
+    }
+
+    #[test]
+    fn sorted_view_is_ascending_with_final_rank_one() {
+        let mut s = KllFloatSketch::new();
+        for i in 1..=10 {
+            s.update(i as f32);
+        }
+        let view: Vec<_> = s.sorted_view().collect();
+        assert_eq!(view.len(), 10);
+        assert!(view.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(view.last().unwrap().2, 1.0);
+    }
+
+    #[test]
+    fn cdf_matches_get_rank_at_split_points() {
+        let mut s = KllFloatSketch::new();
+        for i in 1..=100 {
+            s.update(i as f32);
+        }
+        let splits = [25.0, 50.0, 75.0];
+        let cdf = s.get_cdf(&splits).unwrap();
+        assert_eq!(cdf.len(), 4);
+        for (sp, c) in splits.iter().zip(&cdf) {
+            assert!((s.get_rank(*sp) - c).abs() < 1e-9);
+        }
+        assert_eq!(*cdf.last().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn pmf_sums_to_one() {
+        let mut s = KllFloatSketch::new();
+        for i in 1..=100 {
+            s.update(i as f32);
+        }
+        let pmf = s.get_pmf(&[25.0, 50.0, 75.0]).unwrap();
+        let sum: f64 = pmf.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-9, "sum was {sum}");
+    }
+
+    #[test]
+    fn cdf_rejects_unsorted_split_points() {
+        let mut s = KllFloatSketch::new();
+        s.update(1.0);
+        assert!(s.get_cdf(&[5.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn cdf_rejects_nan_split_points() {
+        let mut s = KllFloatSketch::new();
+        s.update(1.0);
+        assert!(s.get_cdf(&[f32::NAN]).is_err());
+    }
+
+    #[test]
+    fn ks_test_rejects_an_out_of_range_significance_level() {
+        let mut a = KllDoubleSketch::new();
+        let mut b = KllDoubleSketch::new();
+        a.update(1.0);
+        b.update(2.0);
+        assert!(a.ks_test(&b, 0.0).is_err());
+        assert!(a.ks_test(&b, 1.0).is_err());
+        assert!(a.ks_test(&b, -0.5).is_err());
+        assert!(a.ks_test(&b, 2.0).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use std::cmp::Ordering;
+
+/// The element types a [`super::KllSketch`] can store.
+///
+/// Implemented for `f32` and `f64` only: the KLL algorithm compares items
+/// with a total order, so NaN handling is pinned down via `total_cmp`
+/// rather than `PartialOrd`.
+pub trait KllNumber: Copy + PartialEq + Send + Sync + 'static {
+    /// Width in bytes of this type's wire representation.
+    const WIRE_LEN: usize;
+
+    fn total_cmp(&self, other: &Self) -> Ordering;
+    fn as_f64(self) -> f64;
+    fn to_wire_bytes(self) -> Vec<u8>;
+    fn from_wire_bytes(bytes: &[u8]) -> Self;
+}
+
+impl KllNumber for f32 {
+    const WIRE_LEN: usize = 4;
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn to_wire_bytes(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_wire_bytes(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes.try_into().expect("4 bytes for f32"))
+    }
+}
+
+impl KllNumber for f64 {
+    const WIRE_LEN: usize = 8;
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+
+    fn as_f64(self) -> f64 {
+        self
+    }
+
+    fn to_wire_bytes(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_wire_bytes(bytes: &[u8]) -> Self {
+        f64::from_le_bytes(bytes.try_into().expect("8 bytes for f64"))
+    }
+}
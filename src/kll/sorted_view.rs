@@ -0,0 +1,35 @@
+use super::KllNumber;
+
+/// An iterator over a sketch's retained items in ascending order, each
+/// paired with its cumulative weight and normalized rank.
+///
+/// Building this once and walking it is cheaper than repeated
+/// `get_quantile`/`get_rank` calls, which each re-walk every compactor
+/// level from scratch.
+pub struct SortedView<T: KllNumber> {
+    items: Vec<(T, u64)>,
+    n: u64,
+    pos: usize,
+}
+
+impl<T: KllNumber> SortedView<T> {
+    pub(crate) fn new(items: Vec<(T, u64)>, n: u64) -> Self {
+        Self { items, n, pos: 0 }
+    }
+}
+
+impl<T: KllNumber> Iterator for SortedView<T> {
+    /// `(value, cumulative_weight, rank)`.
+    type Item = (T, u64, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (value, cumulative_weight) = *self.items.get(self.pos)?;
+        self.pos += 1;
+        let rank = if self.n == 0 {
+            0.0
+        } else {
+            cumulative_weight as f64 / self.n as f64
+        };
+        Some((value, cumulative_weight, rank))
+    }
+}
@@ -0,0 +1,182 @@
+//! Sliding-window quantile tracking.
+//!
+//! [`WindowedKllFloatSketch`] keeps a ring of per-bucket KLL sub-sketches,
+//! one per fixed-size time slice. `advance_to` drops whole buckets once
+//! they fall entirely outside the live window, so memory stays bounded by
+//! one more than `window / bucket_duration` (rounded up) sub-sketches (the
+//! extra one is the bucket straddling the cutoff) regardless of how long the
+//! stream runs. Queries merge only the buckets currently in the window,
+//! never a bucket that has expired.
+
+use std::collections::VecDeque;
+
+use super::{KllFloatSketch, DEFAULT_K};
+
+struct Bucket {
+    start: u64,
+    sketch: KllFloatSketch,
+}
+
+/// Approximate quantiles over a sliding time window, e.g. a rolling 24-hour
+/// 99th percentile for a sensor or price feed.
+pub struct WindowedKllFloatSketch {
+    k: u16,
+    window: u64,
+    bucket_duration: u64,
+    buckets: VecDeque<Bucket>,
+    current_time: u64,
+}
+
+impl WindowedKllFloatSketch {
+    /// Creates a sketch tracking a window of `window` time units, divided
+    /// into buckets of `bucket_duration` time units each (smaller buckets
+    /// give finer-grained expiry at the cost of more retained sub-sketches).
+    pub fn new(window: u64, bucket_duration: u64) -> Self {
+        Self::with_k(window, bucket_duration, DEFAULT_K)
+    }
+
+    /// Like [`WindowedKllFloatSketch::new`], with an explicit per-bucket `k`.
+    pub fn with_k(window: u64, bucket_duration: u64, k: u16) -> Self {
+        Self {
+            k,
+            window,
+            bucket_duration: bucket_duration.max(1),
+            buckets: VecDeque::new(),
+            current_time: 0,
+        }
+    }
+
+    /// Adds `value` observed at `timestamp`. Expires stale buckets first,
+    /// then drops the update if it still falls outside the live window
+    /// (i.e. it arrived too late).
+    pub fn update(&mut self, value: f32, timestamp: u64) {
+        self.advance_to(timestamp);
+        let bucket_start = self.bucket_start(timestamp);
+        if bucket_start + self.bucket_duration <= self.cutoff() {
+            return;
+        }
+        if let Some(bucket) = self.buckets.iter_mut().find(|b| b.start == bucket_start) {
+            bucket.sketch.update(value);
+            return;
+        }
+        let idx = self.buckets.partition_point(|b| b.start < bucket_start);
+        let mut sketch = KllFloatSketch::with_k(self.k);
+        sketch.update(value);
+        self.buckets.insert(idx, Bucket {
+            start: bucket_start,
+            sketch,
+        });
+    }
+
+    /// Advances the sketch's notion of "now" to `timestamp`, expiring every
+    /// bucket whose time range no longer overlaps the live window. Buckets
+    /// are dropped whole, never partially merged.
+    pub fn advance_to(&mut self, timestamp: u64) {
+        self.current_time = self.current_time.max(timestamp);
+        let cutoff = self.cutoff();
+        while let Some(front) = self.buckets.front() {
+            if front.start + self.bucket_duration <= cutoff {
+                self.buckets.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Total number of updates currently live in the window.
+    pub fn get_n(&self) -> u64 {
+        self.buckets.iter().map(|b| b.sketch.get_n()).sum()
+    }
+
+    /// Estimates the value at normalized rank `rank` over the live window.
+    /// Returns `None` if the window is empty (no updates yet, or every
+    /// bucket has expired).
+    pub fn get_quantile(&self, rank: f64) -> Option<f32> {
+        self.merged().get_quantile(rank).ok()
+    }
+
+    /// Estimates the normalized rank of `value` over the live window.
+    pub fn get_rank(&self, value: f32) -> f64 {
+        self.merged().get_rank(value)
+    }
+
+    fn cutoff(&self) -> u64 {
+        self.current_time.saturating_sub(self.window)
+    }
+
+    fn bucket_start(&self, timestamp: u64) -> u64 {
+        (timestamp / self.bucket_duration) * self.bucket_duration
+    }
+
+    /// Merges every live bucket's sub-sketch into one combined sketch, only
+    /// ever combining sub-sketches whose windows currently overlap the
+    /// query range.
+    fn merged(&self) -> KllFloatSketch {
+        let mut merged = KllFloatSketch::with_k(self.k);
+        for bucket in &self.buckets {
+            merged.merge(&bucket.sketch);
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_within_the_window_are_counted() {
+        let mut s = WindowedKllFloatSketch::new(100, 10);
+        for t in 0..50 {
+            s.update(t as f32, t);
+        }
+        assert_eq!(s.get_n(), 50);
+    }
+
+    #[test]
+    fn advancing_past_the_window_expires_old_buckets() {
+        let mut s = WindowedKllFloatSketch::new(100, 10);
+        for t in 0..10 {
+            s.update(0.0, t);
+        }
+        s.advance_to(500);
+        assert_eq!(s.get_n(), 0);
+    }
+
+    #[test]
+    fn rolling_quantile_reflects_only_recent_data() {
+        let mut s = WindowedKllFloatSketch::new(100, 10);
+        for t in 0..100 {
+            s.update(1.0, t);
+        }
+        // Push the window forward so nearly all the old data (1.0s) expires:
+        // only the bucket straddling the cutoff (t = 90..99) stays live, and
+        // the 100 newer, higher readings outnumber its 10 values.
+        for t in 100..200 {
+            s.update(100.0, t);
+        }
+        assert!(s.get_quantile(0.5).unwrap() > 50.0);
+    }
+
+    #[test]
+    fn updates_older_than_the_window_are_dropped() {
+        let mut s = WindowedKllFloatSketch::new(50, 10);
+        s.update(1.0, 1000);
+        s.update(2.0, 0); // far outside the live window now
+        assert_eq!(s.get_n(), 1);
+    }
+
+    #[test]
+    fn quantile_queries_on_an_empty_or_expired_window_return_none() {
+        let fresh = WindowedKllFloatSketch::new(100, 10);
+        assert_eq!(fresh.get_quantile(0.5), None);
+
+        let mut expired = WindowedKllFloatSketch::new(100, 10);
+        for t in 0..10 {
+            expired.update(0.0, t);
+        }
+        expired.advance_to(500);
+        assert_eq!(expired.get_n(), 0);
+        assert_eq!(expired.get_quantile(0.5), None);
+    }
+}
@@ -0,0 +1,16 @@
+//! `dsrs`: streaming sketch algorithms (quantiles, distinct counting, heavy
+//! hitters, sampling) in the spirit of Apache DataSketches.
+
+pub mod bytes;
+pub mod error;
+pub mod frequent_items;
+pub mod kll;
+pub mod theta;
+pub mod varopt;
+
+pub use bytes::NativeBytes;
+pub use error::SketchError;
+pub use frequent_items::{ErrorType, FrequentItemsSketch};
+pub use kll::{KllDoubleSketch, KllFloatSketch, WindowedKllFloatSketch};
+pub use theta::ThetaSketch;
+pub use varopt::VarOptSketch;
@@ -0,0 +1,303 @@
+//! Theta sketches for distinct counting and set operations.
+//!
+//! A theta sketch retains the `k` smallest 64-bit hashes seen so far (out of
+//! the full `[0, u64::MAX]` hash space) and tracks `theta`, the fraction of
+//! that space still retained. Because hashing spreads items uniformly,
+//! `|retained| / theta` is an unbiased estimate of the number of distinct
+//! items. Unlike the KLL quantile sketches, two theta sketches over the
+//! same hash space can be combined with set semantics, not just merged.
+
+use std::collections::BTreeSet;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bytes::NativeBytes;
+use crate::error::Result;
+
+/// Default `lg_k`: nominal entries of `2^12 = 4096`, matching the Apache
+/// DataSketches default.
+pub const DEFAULT_LG_K: u8 = 12;
+
+const MIN_LG_K: u8 = 4;
+
+/// A sketch over `theta`-compatible 64-bit hashes supporting approximate
+/// distinct counting and set operations (`union`, `intersect`, `a_not_b`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThetaSketch {
+    lg_k: u8,
+    theta: u64,
+    hashes: BTreeSet<u64>,
+}
+
+impl ThetaSketch {
+    /// Creates a sketch with the default `lg_k` (nominal entries `2^12`).
+    pub fn new() -> Self {
+        Self::with_lg_k(DEFAULT_LG_K)
+    }
+
+    /// Creates a sketch whose retained set is bounded at `2^lg_k` nominal
+    /// entries.
+    pub fn with_lg_k(lg_k: u8) -> Self {
+        let lg_k = lg_k.max(MIN_LG_K);
+        Self {
+            lg_k,
+            theta: u64::MAX,
+            hashes: BTreeSet::new(),
+        }
+    }
+
+    /// Adds an item to the sketch.
+    pub fn update<T: Hash>(&mut self, item: T) {
+        let h = Self::hash_of(&item);
+        if h < self.theta {
+            self.hashes.insert(h);
+            self.trim();
+        }
+    }
+
+    /// Estimated number of distinct items seen.
+    pub fn get_estimate(&self) -> f64 {
+        let theta_fraction = self.theta_fraction();
+        if theta_fraction <= 0.0 {
+            return 0.0;
+        }
+        self.hashes.len() as f64 / theta_fraction
+    }
+
+    /// Approximate lower bound on the true distinct count, `num_std_devs`
+    /// standard deviations below the estimate (typically 1-3).
+    pub fn get_lower_bound(&self, num_std_devs: u8) -> f64 {
+        let estimate = self.get_estimate();
+        (estimate - self.std_dev(num_std_devs)).max(self.hashes.len() as f64)
+    }
+
+    /// Approximate upper bound on the true distinct count, `num_std_devs`
+    /// standard deviations above the estimate (typically 1-3).
+    pub fn get_upper_bound(&self, num_std_devs: u8) -> f64 {
+        self.get_estimate() + self.std_dev(num_std_devs)
+    }
+
+    /// The retained `lg_k`: `2^lg_k` is the nominal entries bound.
+    pub fn get_lg_k(&self) -> u8 {
+        self.lg_k
+    }
+
+    /// Number of hashes currently retained.
+    pub fn get_num_retained(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns a new sketch estimating `|self ∪ other|`.
+    pub fn union(&self, other: &Self) -> Self {
+        let new_theta = self.theta.min(other.theta);
+        let mut hashes: BTreeSet<u64> = self
+            .hashes
+            .iter()
+            .chain(other.hashes.iter())
+            .copied()
+            .filter(|h| *h < new_theta)
+            .collect();
+        let lg_k = self.lg_k.max(other.lg_k);
+        let mut result = Self {
+            lg_k,
+            theta: new_theta,
+            hashes: std::mem::take(&mut hashes),
+        };
+        result.trim();
+        result
+    }
+
+    /// Returns a new sketch estimating `|self ∩ other|`.
+    pub fn intersect(&self, other: &Self) -> Self {
+        let new_theta = self.theta.min(other.theta);
+        let hashes = self
+            .hashes
+            .iter()
+            .filter(|h| **h < new_theta && other.hashes.contains(*h))
+            .copied()
+            .collect();
+        Self {
+            lg_k: self.lg_k.min(other.lg_k),
+            theta: new_theta,
+            hashes,
+        }
+    }
+
+    /// Returns a new sketch estimating `|self \ other|` (items in `self`
+    /// but not `other`).
+    pub fn a_not_b(&self, other: &Self) -> Self {
+        let new_theta = self.theta.min(other.theta);
+        let hashes = self
+            .hashes
+            .iter()
+            .filter(|h| **h < new_theta && !other.hashes.contains(*h))
+            .copied()
+            .collect();
+        Self {
+            lg_k: self.lg_k,
+            theta: new_theta,
+            hashes,
+        }
+    }
+
+    /// Serializes the sketch to dsrs's native binary format.
+    pub fn serialize(&self) -> NativeBytes {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.lg_k.to_le_bytes());
+        buf.extend_from_slice(&self.theta.to_le_bytes());
+        buf.extend_from_slice(&(self.hashes.len() as u32).to_le_bytes());
+        for h in &self.hashes {
+            buf.extend_from_slice(&h.to_le_bytes());
+        }
+        NativeBytes(buf)
+    }
+
+    /// Serializes the sketch as MessagePack.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec(self)?)
+    }
+
+    /// Deserializes a sketch previously produced by
+    /// [`ThetaSketch::to_msgpack`].
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    fn hash_of<T: Hash>(item: &T) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        item.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn theta_fraction(&self) -> f64 {
+        self.theta as f64 / u64::MAX as f64
+    }
+
+    fn std_dev(&self, num_std_devs: u8) -> f64 {
+        let theta_fraction = self.theta_fraction();
+        if self.hashes.is_empty() || theta_fraction >= 1.0 {
+            return 0.0;
+        }
+        let estimate = self.get_estimate();
+        let variance = estimate * (1.0 - theta_fraction) / theta_fraction;
+        num_std_devs as f64 * variance.sqrt()
+    }
+
+    /// If the retained set has grown past the nominal entries bound,
+    /// lowers `theta` to the `(k + 1)`-th smallest retained hash and drops
+    /// everything at or above it, leaving exactly `k` hashes.
+    fn trim(&mut self) {
+        let k = 1usize << self.lg_k;
+        if self.hashes.len() <= k {
+            return;
+        }
+        let new_theta = *self.hashes.iter().nth(k).expect("len > k checked above");
+        self.theta = new_theta;
+        self.hashes.retain(|h| *h < new_theta);
+    }
+}
+
+impl Default for ThetaSketch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_sketch_estimates_zero() {
+        let s = ThetaSketch::new();
+        assert_eq!(s.get_estimate(), 0.0);
+    }
+
+    #[test]
+    fn exact_mode_counts_are_precise() {
+        let mut s = ThetaSketch::new();
+        for i in 0..100 {
+            s.update(i);
+        }
+        assert_eq!(s.get_estimate(), 100.0);
+        assert_eq!(s.get_num_retained(), 100);
+    }
+
+    #[test]
+    fn duplicate_updates_do_not_inflate_the_estimate() {
+        let mut s = ThetaSketch::new();
+        for _ in 0..5 {
+            for i in 0..50 {
+                s.update(i);
+            }
+        }
+        assert_eq!(s.get_estimate(), 50.0);
+    }
+
+    #[test]
+    fn large_stream_estimate_is_approximately_correct() {
+        let mut s = ThetaSketch::with_lg_k(10);
+        for i in 0..200_000 {
+            s.update(i);
+        }
+        let estimate = s.get_estimate();
+        let relative_error = (estimate - 200_000.0).abs() / 200_000.0;
+        assert!(relative_error < 0.1, "estimate was {estimate}");
+        assert!(s.get_lower_bound(2) <= estimate);
+        assert!(s.get_upper_bound(2) >= estimate);
+    }
+
+    #[test]
+    fn union_estimates_the_combined_distinct_count() {
+        let mut a = ThetaSketch::new();
+        let mut b = ThetaSketch::new();
+        for i in 0..100 {
+            a.update(i);
+        }
+        for i in 50..150 {
+            b.update(i);
+        }
+        let u = a.union(&b);
+        assert_eq!(u.get_estimate(), 150.0);
+    }
+
+    #[test]
+    fn intersect_estimates_the_overlap() {
+        let mut a = ThetaSketch::new();
+        let mut b = ThetaSketch::new();
+        for i in 0..100 {
+            a.update(i);
+        }
+        for i in 50..150 {
+            b.update(i);
+        }
+        let overlap = a.intersect(&b);
+        assert_eq!(overlap.get_estimate(), 50.0);
+    }
+
+    #[test]
+    fn a_not_b_estimates_the_set_difference() {
+        let mut a = ThetaSketch::new();
+        let mut b = ThetaSketch::new();
+        for i in 0..100 {
+            a.update(i);
+        }
+        for i in 50..150 {
+            b.update(i);
+        }
+        let diff = a.a_not_b(&b);
+        assert_eq!(diff.get_estimate(), 50.0);
+    }
+
+    #[test]
+    fn msgpack_round_trip_preserves_estimate() {
+        let mut s = ThetaSketch::new();
+        for i in 0..1000 {
+            s.update(i);
+        }
+        let bytes = s.to_msgpack().unwrap();
+        let recovered = ThetaSketch::from_msgpack(&bytes).unwrap();
+        assert_eq!(s.get_estimate(), recovered.get_estimate());
+    }
+}
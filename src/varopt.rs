@@ -0,0 +1,245 @@
+//! Variance-optimal (VarOpt) weighted sampling.
+//!
+//! Maintains a bounded reservoir split into a "heavy" region (items whose
+//! weight is large enough that they are kept with their exact weight) and a
+//! "light" region (items represented by a shared weight `tau`). On overflow,
+//! the heavy/light partition is recomputed from scratch over every retained
+//! item plus the new arrival: any item whose real weight would exceed a
+//! shrinking shared `tau` is (re)promoted into the heavy region, and any
+//! previously-heavy item whose weight no longer clears the new `tau` is
+//! demoted back to light, so the boundary tracks the current distribution
+//! rather than freezing early arrivals as heavy forever. Once `tau`
+//! stabilizes, exactly one light item is evicted with probability
+//! proportional to how far its weight falls short of `tau`, and every
+//! surviving light item's reported weight becomes `tau`. This preserves an
+//! unbiased estimator of the sum of any subset of the original stream.
+
+use rand::Rng;
+
+/// A bounded-memory weighted sample of a stream, unbiased for subset-sum
+/// estimation (e.g. "total volume of a representative sample of trades").
+pub struct VarOptSketch<T: Clone> {
+    k: usize,
+    heavy: Vec<(T, f64)>,
+    light: Vec<(T, f64)>,
+    tau: f64,
+    n: u64,
+}
+
+impl<T: Clone> VarOptSketch<T> {
+    /// Creates a sketch with a reservoir of size `k`.
+    pub fn new(k: usize) -> Self {
+        Self {
+            k: k.max(1),
+            heavy: Vec::new(),
+            light: Vec::new(),
+            tau: 0.0,
+            n: 0,
+        }
+    }
+
+    /// Adds `item` with the given `weight` (must be positive).
+    pub fn update(&mut self, item: T, weight: f64) {
+        self.n += 1;
+        self.insert(item, weight);
+    }
+
+    /// Inserts `item` into the light region and rebalances on overflow,
+    /// without touching `n`. Shared by `update` (which counts one arrival
+    /// per call) and `merge` (which counts `other.n` arrivals up front,
+    /// independent of how many samples are replayed).
+    fn insert(&mut self, item: T, weight: f64) {
+        self.light.push((item, weight));
+        if self.heavy.len() + self.light.len() > self.k {
+            self.rebalance();
+        }
+    }
+
+    /// Number of updates seen (including duplicates and evicted items).
+    pub fn get_n(&self) -> u64 {
+        self.n
+    }
+
+    /// Number of items currently retained (at most `k`).
+    pub fn get_num_retained(&self) -> usize {
+        self.heavy.len() + self.light.len()
+    }
+
+    /// Returns the current sample: `(item, adjusted_weight)` pairs. Until the
+    /// reservoir first overflows, every item carries its real weight; after
+    /// that, heavy items carry their real weight and light items carry the
+    /// shared `tau`.
+    pub fn get_samples(&self) -> Vec<(T, f64)> {
+        self.heavy.iter().chain(self.light.iter()).cloned().collect()
+    }
+
+    /// Merges `other`'s sample back in, replaying heavy items at their real
+    /// weight and light items at their current `tau`-adjusted weight. This
+    /// keeps the combined reservoir within `k` but, unlike `update`-by-update
+    /// merging of the same stream, is an approximation: a light item's
+    /// original weight is already lost by the time it reaches this sketch.
+    /// `n` is incremented by `other.n` directly, not by the number of
+    /// samples replayed, so it stays a true count of the combined stream
+    /// length regardless of how much either sketch has downsampled.
+    pub fn merge(&mut self, other: &Self) {
+        for (item, weight) in other.get_samples() {
+            self.insert(item, weight);
+        }
+        self.n += other.n;
+    }
+
+    /// Recomputes the heavy/light partition from scratch over every
+    /// retained item (heavy items are pulled back into contention, not kept
+    /// frozen), so a stale heavy item below the new `tau` is demoted instead
+    /// of permanently outranking lighter-seeming items.
+    fn rebalance(&mut self) {
+        self.light.append(&mut self.heavy);
+        let mut rng = rand::thread_rng();
+        loop {
+            let slots = self.k.saturating_sub(self.heavy.len());
+            if self.light.len() <= slots {
+                return;
+            }
+            let target_light_count = self.light.len() - 1;
+            let total_light_weight: f64 = self.light.iter().map(|(_, w)| w).sum();
+            let candidate_tau = total_light_weight / target_light_count.max(1) as f64;
+
+            let largest_idx = self
+                .light
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1 .1.total_cmp(&b.1 .1))
+                .map(|(idx, _)| idx);
+            if let Some(idx) = largest_idx {
+                if self.light[idx].1 > candidate_tau {
+                    let promoted = self.light.remove(idx);
+                    self.heavy.push(promoted);
+                    continue;
+                }
+            }
+
+            self.tau = candidate_tau;
+            self.evict_one_light_item(&mut rng);
+            return;
+        }
+    }
+
+    /// Evicts exactly one light item with probability `(tau - weight) / tau`
+    /// (these sum to exactly 1 by construction of `tau`), then sets every
+    /// surviving light item's reported weight to `tau`.
+    fn evict_one_light_item(&mut self, rng: &mut impl Rng) {
+        if self.tau <= 0.0 || self.light.is_empty() {
+            self.light.clear();
+            return;
+        }
+        let mut draw = rng.gen::<f64>();
+        let mut evict_idx = self.light.len() - 1;
+        for (idx, (_, weight)) in self.light.iter().enumerate() {
+            let evict_prob = (self.tau - weight) / self.tau;
+            if draw < evict_prob {
+                evict_idx = idx;
+                break;
+            }
+            draw -= evict_prob;
+        }
+        self.light.remove(evict_idx);
+        for (_, weight) in &mut self.light {
+            *weight = self.tau;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn under_capacity_keeps_everything_at_exact_weight() {
+        let mut s = VarOptSketch::new(10);
+        for i in 0..5 {
+            s.update(i, (i + 1) as f64);
+        }
+        assert_eq!(s.get_num_retained(), 5);
+        let total: f64 = s.get_samples().iter().map(|(_, w)| w).sum();
+        assert_eq!(total, 15.0);
+    }
+
+    #[test]
+    fn reservoir_never_exceeds_k() {
+        let mut s = VarOptSketch::new(20);
+        for i in 0..10_000 {
+            s.update(i, ((i % 100) + 1) as f64);
+        }
+        assert!(s.get_num_retained() <= 20);
+        assert_eq!(s.get_n(), 10_000);
+    }
+
+    #[test]
+    fn an_overwhelmingly_heavy_item_always_survives() {
+        const HEAVY_ID: i64 = -1;
+        let mut s = VarOptSketch::new(8);
+        s.update(HEAVY_ID, 1_000_000.0);
+        for i in 0..1000 {
+            s.update(i, 1.0);
+        }
+        let heavy_present = s.get_samples().iter().any(|(item, _)| *item == HEAVY_ID);
+        assert!(heavy_present);
+    }
+
+    #[test]
+    fn merge_keeps_reservoir_bounded() {
+        let mut a = VarOptSketch::new(10);
+        let mut b = VarOptSketch::new(10);
+        for i in 0..100 {
+            a.update(i, 1.0);
+        }
+        for i in 100..200 {
+            b.update(i, 1.0);
+        }
+        a.merge(&b);
+        assert!(a.get_num_retained() <= 10);
+    }
+
+    #[test]
+    fn merge_counts_n_as_the_combined_stream_length() {
+        let mut a = VarOptSketch::new(10);
+        let mut b = VarOptSketch::new(10);
+        for i in 0..100 {
+            a.update(i, 1.0);
+        }
+        for i in 100..200 {
+            b.update(i, 1.0);
+        }
+        a.merge(&b);
+        assert_eq!(a.get_n(), 200);
+    }
+
+    #[test]
+    fn a_formerly_heavy_item_can_be_demoted_once_tau_outgrows_it() {
+        const ONCE_HEAVY_ID: i64 = -1;
+        let mut s = VarOptSketch::new(4);
+        // A moderately heavy item relative to the first few light items.
+        s.update(ONCE_HEAVY_ID, 10.0);
+        for i in 0..3 {
+            s.update(i, 1.0);
+        }
+        assert!(s
+            .get_samples()
+            .iter()
+            .any(|(item, weight)| *item == ONCE_HEAVY_ID && *weight == 10.0));
+
+        // Flood in much heavier items so tau eventually grows past 10.0;
+        // ONCE_HEAVY_ID must fall back into the light, tau-weighted region
+        // rather than staying frozen at its original exact weight forever.
+        for i in 0..1000 {
+            s.update(i + 3, 1_000_000.0);
+        }
+        let sample = s
+            .get_samples()
+            .into_iter()
+            .find(|(item, _)| *item == ONCE_HEAVY_ID);
+        if let Some((_, weight)) = sample {
+            assert_ne!(weight, 10.0, "should have been demoted to the shared tau");
+        }
+    }
+}